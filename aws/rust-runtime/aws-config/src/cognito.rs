@@ -0,0 +1,281 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0.
+ */
+
+//! A [`ProvideCredentials`] backed by Amazon Cognito Identity, for mobile and other federated
+//! scenarios that exchange an identity pool ID (and, optionally, a set of `Logins`) for
+//! temporary credentials via `GetId` followed by `GetCredentialsForIdentity`.
+
+use std::collections::HashMap;
+use std::error::Error as StdError;
+use std::fmt;
+use std::time::SystemTime;
+
+use aws_types::credential::{self, future, CredentialsError, ProvideCredentials};
+use aws_types::region::Region;
+use aws_types::Credentials;
+use smithy_client::erase::DynConnector;
+
+/// A [`ProvideCredentials`] implementation for Amazon Cognito Identity.
+///
+/// Resolves credentials for an identity pool by calling `GetId` (optionally passing `logins` for
+/// an authenticated identity and `account_id` when the pool requires it) and then exchanging the
+/// resulting identity ID for temporary credentials via `GetCredentialsForIdentity`.
+#[derive(Debug)]
+pub struct CognitoIdentityCredentialsProvider {
+    identity_pool_id: String,
+    account_id: Option<String>,
+    logins: HashMap<String, String>,
+    connector: DynConnector,
+    region: Option<Region>,
+}
+
+impl CognitoIdentityCredentialsProvider {
+    /// Returns a builder for configuring a [`CognitoIdentityCredentialsProvider`].
+    pub fn builder() -> Builder {
+        Builder::default()
+    }
+
+    async fn credentials(&self) -> credential::Result {
+        let config = aws_sdk_cognitoidentity::Config::builder()
+            .region(self.region.clone())
+            .build();
+        let client = aws_sdk_cognitoidentity::RawClient::from_conf_conn(
+            config.clone(),
+            self.connector.clone(),
+        );
+        let logins = if self.logins.is_empty() {
+            None
+        } else {
+            Some(self.logins.clone())
+        };
+
+        let get_id = aws_sdk_cognitoidentity::operation::GetId::builder()
+            .identity_pool_id(&self.identity_pool_id)
+            .set_account_id(self.account_id.clone())
+            .set_logins(logins.clone())
+            .build()
+            .expect("valid operation")
+            .make_operation(&config)
+            .expect("valid operation");
+        let identity_id = client
+            .call(get_id)
+            .await
+            .map_err(|err| CredentialsError::ProviderError(err.into()))?
+            .identity_id
+            .ok_or_else(|| {
+                CredentialsError::ProviderError(Box::new(CognitoIdentityError::MissingField(
+                    "IdentityId",
+                )))
+            })?;
+
+        let get_credentials = aws_sdk_cognitoidentity::operation::GetCredentialsForIdentity::builder()
+            .identity_id(&identity_id)
+            .set_logins(logins)
+            .build()
+            .expect("valid operation")
+            .make_operation(&config)
+            .expect("valid operation");
+        let credentials = client
+            .call(get_credentials)
+            .await
+            .map_err(|err| CredentialsError::ProviderError(err.into()))?
+            .credentials
+            .ok_or_else(|| {
+                CredentialsError::ProviderError(Box::new(CognitoIdentityError::MissingField(
+                    "Credentials",
+                )))
+            })?;
+
+        let expiration = credentials
+            .expiration
+            .map(|exp| {
+                SystemTime::try_from(exp).map_err(|_| {
+                    CredentialsError::ProviderError(Box::new(
+                        CognitoIdentityError::InvalidExpiration,
+                    ))
+                })
+            })
+            .transpose()?;
+
+        Ok(Credentials::new(
+            credentials.access_key_id.ok_or_else(|| {
+                CredentialsError::ProviderError(Box::new(CognitoIdentityError::MissingField(
+                    "AccessKeyId",
+                )))
+            })?,
+            credentials.secret_key.ok_or_else(|| {
+                CredentialsError::ProviderError(Box::new(CognitoIdentityError::MissingField(
+                    "SecretKey",
+                )))
+            })?,
+            credentials.session_token,
+            expiration,
+            "CognitoIdentityCredentialsProvider",
+        ))
+    }
+}
+
+impl ProvideCredentials for CognitoIdentityCredentialsProvider {
+    fn provide_credentials<'a>(&'a self) -> future::ProvideCredentials<'a>
+    where
+        Self: 'a,
+    {
+        future::ProvideCredentials::new(self.credentials())
+    }
+}
+
+#[derive(Debug)]
+enum CognitoIdentityError {
+    MissingField(&'static str),
+    InvalidExpiration,
+}
+
+impl fmt::Display for CognitoIdentityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CognitoIdentityError::InvalidExpiration => write!(
+                f,
+                "Cognito Identity returned a credentials `Expiration` that could not be \
+                 converted to a system time"
+            ),
+            CognitoIdentityError::MissingField(field) => write!(
+                f,
+                "Cognito Identity response was missing the expected `{}` field",
+                field
+            ),
+        }
+    }
+}
+
+impl StdError for CognitoIdentityError {}
+
+/// Builder for [`CognitoIdentityCredentialsProvider`].
+#[derive(Default)]
+pub struct Builder {
+    identity_pool_id: Option<String>,
+    account_id: Option<String>,
+    logins: HashMap<String, String>,
+    connector: Option<DynConnector>,
+    region: Option<Region>,
+}
+
+impl Builder {
+    /// Sets the Cognito identity pool ID, e.g. `us-east-1:12345678-1234-1234-1234-123456789012`.
+    pub fn identity_pool_id(mut self, identity_pool_id: impl Into<String>) -> Self {
+        self.identity_pool_id = Some(identity_pool_id.into());
+        self
+    }
+
+    /// Sets the AWS account ID that owns the identity pool. Only required for pools that were
+    /// configured to require it.
+    pub fn account_id(mut self, account_id: impl Into<String>) -> Self {
+        self.account_id = Some(account_id.into());
+        self
+    }
+
+    /// Adds a login provider token, e.g. `("graph.facebook.com", "<token>")`, for an
+    /// authenticated identity. May be called multiple times for multiple providers.
+    pub fn login(mut self, provider: impl Into<String>, token: impl Into<String>) -> Self {
+        self.logins.insert(provider.into(), token.into());
+        self
+    }
+
+    /// Sets the connector used to call Cognito Identity.
+    pub fn connector(mut self, connector: DynConnector) -> Self {
+        self.connector = Some(connector);
+        self
+    }
+
+    /// Sets the region to call Cognito Identity in.
+    pub fn region(mut self, region: Option<Region>) -> Self {
+        self.region = region;
+        self
+    }
+
+    /// Builds a [`CognitoIdentityCredentialsProvider`].
+    ///
+    /// # Panics
+    /// Panics if `identity_pool_id` or `connector` were never set.
+    pub fn build(self) -> CognitoIdentityCredentialsProvider {
+        CognitoIdentityCredentialsProvider {
+            identity_pool_id: self.identity_pool_id.expect("identity_pool_id is required"),
+            account_id: self.account_id,
+            logins: self.logins,
+            connector: self.connector.expect("connector is required"),
+            region: self.region,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::ScriptedConnection;
+
+    const GET_ID_RESPONSE: &str = r#"{"IdentityId":"us-east-1:test-identity"}"#;
+    const GET_CREDENTIALS_RESPONSE: &str = r#"{
+        "IdentityId": "us-east-1:test-identity",
+        "Credentials": {
+            "AccessKeyId": "AKID",
+            "SecretKey": "secret",
+            "SessionToken": "session-token",
+            "Expiration": 4070908800
+        }
+    }"#;
+
+    fn scripted_connection() -> ScriptedConnection {
+        ScriptedConnection::new(vec![
+            Ok(http::Response::builder()
+                .status(200)
+                .body(smithy_http::body::SdkBody::from(GET_ID_RESPONSE))
+                .unwrap()),
+            Ok(http::Response::builder()
+                .status(200)
+                .body(smithy_http::body::SdkBody::from(GET_CREDENTIALS_RESPONSE))
+                .unwrap()),
+        ])
+    }
+
+    #[tokio::test]
+    async fn unauthenticated_identity() {
+        let connection = scripted_connection();
+        let provider = CognitoIdentityCredentialsProvider::builder()
+            .identity_pool_id("us-east-1:test-pool")
+            .connector(DynConnector::new(connection))
+            .region(Some(Region::new("us-east-1")))
+            .build();
+
+        provider
+            .provide_credentials()
+            .await
+            .expect("unauthenticated identity resolves to credentials");
+    }
+
+    #[tokio::test]
+    async fn logins_are_forwarded_to_both_calls() {
+        let connection = scripted_connection();
+        let provider = CognitoIdentityCredentialsProvider::builder()
+            .identity_pool_id("us-east-1:test-pool")
+            .login("graph.facebook.com", "test-token")
+            .connector(DynConnector::new(connection.clone()))
+            .region(Some(Region::new("us-east-1")))
+            .build();
+
+        provider
+            .provide_credentials()
+            .await
+            .expect("authenticated identity with logins resolves to credentials");
+
+        let sent = connection.requests();
+        for request in &sent {
+            let body = String::from_utf8(request.body().clone()).unwrap();
+            assert!(
+                body.contains("graph.facebook.com") && body.contains("test-token"),
+                "request did not forward the configured login: {}",
+                body
+            );
+        }
+    }
+}
@@ -0,0 +1,262 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0.
+ */
+
+//! A [`ProvideCredentials`] that fetches credentials from the ECS (or other container orchestrator)
+//! task metadata credentials endpoint, as described in
+//! <https://docs.aws.amazon.com/AmazonECS/latest/userguide/task-iam-roles.html>.
+
+use std::error::Error as StdError;
+use std::fmt;
+use std::str::FromStr;
+use std::time::SystemTime;
+
+use aws_types::credential::{self, future, CredentialsError, ProvideCredentials};
+use aws_types::os_shim_internal::Env;
+use aws_types::Credentials;
+use smithy_client::erase::DynConnector;
+use smithy_http::body::SdkBody;
+use smithy_types::date_time::{DateTime, Format};
+use tower::Service;
+
+const RELATIVE_URI_ENV: &str = "AWS_CONTAINER_CREDENTIALS_RELATIVE_URI";
+const FULL_URI_ENV: &str = "AWS_CONTAINER_CREDENTIALS_FULL_URI";
+const AUTH_TOKEN_ENV: &str = "AWS_CONTAINER_AUTHORIZATION_TOKEN";
+const CONTAINER_METADATA_BASE_URI: &str = "http://169.254.170.2";
+
+/// A [`ProvideCredentials`] implementation for the ECS container credentials endpoint.
+///
+/// Resolves credentials from `AWS_CONTAINER_CREDENTIALS_RELATIVE_URI` (joined with the fixed
+/// link-local ECS metadata host) or, if set, the full `AWS_CONTAINER_CREDENTIALS_FULL_URI`,
+/// optionally authenticated with `AWS_CONTAINER_AUTHORIZATION_TOKEN`.
+#[derive(Debug)]
+pub struct ContainerCredentialsProvider {
+    connector: DynConnector,
+    env: Env,
+}
+
+impl ContainerCredentialsProvider {
+    /// Returns a builder for configuring a [`ContainerCredentialsProvider`].
+    pub fn builder() -> Builder {
+        Builder::default()
+    }
+
+    async fn credentials(&self) -> credential::Result {
+        let (uri, auth_token) = self.endpoint()?;
+        let mut request_builder = http::Request::builder().uri(uri).method("GET");
+        if let Some(auth_token) = auth_token {
+            request_builder = request_builder.header("Authorization", auth_token);
+        }
+        let request = request_builder
+            .body(SdkBody::empty())
+            .expect("valid request");
+
+        let mut connector = self.connector.clone();
+        let response = connector
+            .call(request)
+            .await
+            .map_err(|err| CredentialsError::ProviderError(err.into()))?;
+        if !response.status().is_success() {
+            return Err(CredentialsError::ProviderError(Box::new(
+                ContainerCredentialsError::ErrorResponse {
+                    status: response.status().as_u16(),
+                },
+            )));
+        }
+
+        let body = hyper::body::to_bytes(response.into_body())
+            .await
+            .map_err(|err| {
+                CredentialsError::ProviderError(Box::new(ContainerCredentialsError::ReadBody(
+                    err.into(),
+                )))
+            })?;
+        let parsed: ContainerResponse = serde_json::from_slice(&body).map_err(|err| {
+            CredentialsError::ProviderError(Box::new(ContainerCredentialsError::InvalidJson(err)))
+        })?;
+
+        let expiry = parse_expiration(&parsed.expiration)?;
+        Ok(Credentials::new(
+            parsed.access_key_id,
+            parsed.secret_access_key,
+            Some(parsed.token),
+            Some(expiry),
+            "ContainerCredentialsProvider",
+        ))
+    }
+
+    fn endpoint(&self) -> Result<(String, Option<String>), CredentialsError> {
+        if let Ok(full_uri) = self.env.get(FULL_URI_ENV) {
+            let auth_token = self.env.get(AUTH_TOKEN_ENV).ok();
+            return Ok((full_uri, auth_token));
+        }
+        if let Ok(relative_uri) = self.env.get(RELATIVE_URI_ENV) {
+            return Ok((format!("{}{}", CONTAINER_METADATA_BASE_URI, relative_uri), None));
+        }
+        Err(CredentialsError::ProviderError(Box::new(
+            ContainerCredentialsError::NotConfigured,
+        )))
+    }
+}
+
+fn parse_expiration(raw: &str) -> Result<SystemTime, CredentialsError> {
+    DateTime::from_str(raw, Format::DateTime)
+        .ok()
+        .and_then(|dt| SystemTime::try_from(dt).ok())
+        .ok_or_else(|| {
+            CredentialsError::ProviderError(Box::new(ContainerCredentialsError::InvalidExpiration(
+                raw.to_string(),
+            )))
+        })
+}
+
+impl ProvideCredentials for ContainerCredentialsProvider {
+    fn provide_credentials<'a>(&'a self) -> future::ProvideCredentials<'a>
+    where
+        Self: 'a,
+    {
+        future::ProvideCredentials::new(self.credentials())
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct ContainerResponse {
+    #[serde(rename = "AccessKeyId")]
+    access_key_id: String,
+    #[serde(rename = "SecretAccessKey")]
+    secret_access_key: String,
+    #[serde(rename = "Token")]
+    token: String,
+    #[serde(rename = "Expiration")]
+    expiration: String,
+}
+
+#[derive(Debug)]
+enum ContainerCredentialsError {
+    NotConfigured,
+    ErrorResponse { status: u16 },
+    ReadBody(Box<dyn StdError + Send + Sync>),
+    InvalidJson(serde_json::Error),
+    InvalidExpiration(String),
+}
+
+impl fmt::Display for ContainerCredentialsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ContainerCredentialsError::NotConfigured => write!(
+                f,
+                "neither `{}` nor `{}` is set; container credentials are not available",
+                RELATIVE_URI_ENV, FULL_URI_ENV
+            ),
+            ContainerCredentialsError::ErrorResponse { status } => {
+                write!(f, "container credentials endpoint returned HTTP {}", status)
+            }
+            ContainerCredentialsError::ReadBody(err) => {
+                write!(f, "failed to read container credentials response body: {}", err)
+            }
+            ContainerCredentialsError::InvalidJson(err) => {
+                write!(f, "container credentials endpoint returned invalid JSON: {}", err)
+            }
+            ContainerCredentialsError::InvalidExpiration(raw) => write!(
+                f,
+                "container credentials endpoint returned an unparseable `Expiration`: `{}`",
+                raw
+            ),
+        }
+    }
+}
+
+impl StdError for ContainerCredentialsError {}
+
+/// Builder for [`ContainerCredentialsProvider`].
+#[derive(Default)]
+pub struct Builder {
+    connector: Option<DynConnector>,
+    env: Option<Env>,
+}
+
+impl Builder {
+    /// Sets the connector used to reach the container metadata endpoint.
+    pub fn connector(mut self, connector: DynConnector) -> Self {
+        self.connector = Some(connector);
+        self
+    }
+
+    /// Overrides the environment used to read `AWS_CONTAINER_CREDENTIALS_*`. Primarily useful
+    /// for testing.
+    pub fn env(mut self, env: Env) -> Self {
+        self.env = Some(env);
+        self
+    }
+
+    /// Builds a [`ContainerCredentialsProvider`].
+    pub fn build(self) -> ContainerCredentialsProvider {
+        ContainerCredentialsProvider {
+            connector: self.connector.expect("a connector must be set"),
+            env: self.env.unwrap_or_default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::ScriptedConnection;
+
+    const CONTAINER_CREDENTIALS_RESPONSE: &str = r#"{
+        "AccessKeyId": "AKID",
+        "SecretAccessKey": "secret",
+        "Token": "session-token",
+        "Expiration": "2099-01-01T00:00:00Z"
+    }"#;
+
+    fn provider_with(env: Env, connection: ScriptedConnection) -> ContainerCredentialsProvider {
+        ContainerCredentialsProvider::builder()
+            .connector(DynConnector::new(connection))
+            .env(env)
+            .build()
+    }
+
+    #[tokio::test]
+    async fn relative_uri_is_joined_with_metadata_host() {
+        let env = Env::from_slice(&[(RELATIVE_URI_ENV, "/v2/credentials/abc")]);
+        let connection = ScriptedConnection::ok(CONTAINER_CREDENTIALS_RESPONSE);
+        let provider = provider_with(env, connection.clone());
+        let creds = provider
+            .provide_credentials()
+            .await
+            .expect("relative URI resolves to the ECS metadata host");
+        assert_eq!("AKID", creds.access_key_id());
+
+        let sent = connection.requests();
+        assert_eq!(
+            "http://169.254.170.2/v2/credentials/abc",
+            sent[0].uri().to_string()
+        );
+    }
+
+    #[tokio::test]
+    async fn full_uri_sends_authorization_header() {
+        let env = Env::from_slice(&[
+            (FULL_URI_ENV, "http://example.com/credentials"),
+            (AUTH_TOKEN_ENV, "secret-token"),
+        ]);
+        let connection = ScriptedConnection::ok(CONTAINER_CREDENTIALS_RESPONSE);
+        let provider = provider_with(env, connection.clone());
+        provider
+            .provide_credentials()
+            .await
+            .expect("full URI with an authorization token succeeds");
+
+        let sent = connection.requests();
+        assert_eq!("http://example.com/credentials", sent[0].uri().to_string());
+        assert_eq!(
+            "secret-token",
+            sent[0]
+                .headers()
+                .get("Authorization")
+                .expect("Authorization header is set")
+        );
+    }
+}
@@ -0,0 +1,178 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0.
+ */
+
+//! Wrappers around [`ProvideCredentials`] that add caching behavior.
+
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use aws_types::credential::{self, future, CredentialsError, ProvideCredentials};
+use aws_types::Credentials;
+
+/// Default amount of time before the stated expiry of a set of credentials that
+/// [`CachingCredentialsProvider`] will proactively refresh them.
+const DEFAULT_BUFFER_TIME: Duration = Duration::from_secs(10 * 60);
+
+/// A [`ProvideCredentials`] implementation that caches the credentials returned by an inner
+/// provider and only refreshes them once they are absent or within a configurable buffer
+/// window of expiring.
+///
+/// Refreshes are single-flight: concurrent callers share the `tokio::sync::Mutex` guarding the
+/// cache, so only the caller that first observes stale (or missing) credentials performs the
+/// refresh; everyone else waits for that result instead of issuing their own call to the
+/// underlying provider.
+pub struct CachingCredentialsProvider {
+    inner: Arc<dyn ProvideCredentials>,
+    buffer_time: Duration,
+    cache: tokio::sync::Mutex<Option<Credentials>>,
+}
+
+impl CachingCredentialsProvider {
+    /// Returns a builder for configuring a [`CachingCredentialsProvider`].
+    pub fn builder() -> Builder {
+        Builder::default()
+    }
+
+    async fn credentials(&self) -> credential::Result {
+        let mut cache = self.cache.lock().await;
+        if let Some(credentials) = cache.as_ref() {
+            if !Self::needs_refresh(credentials, self.buffer_time) {
+                return Ok(credentials.clone());
+            }
+        }
+        let fresh = self.inner.provide_credentials().await?;
+        *cache = Some(fresh.clone());
+        Ok(fresh)
+    }
+
+    /// Credentials without an expiry are assumed to be static (e.g. long-lived access keys) and
+    /// are cached indefinitely.
+    fn needs_refresh(credentials: &Credentials, buffer_time: Duration) -> bool {
+        match credentials.expiry() {
+            None => false,
+            Some(expiry) => match expiry.duration_since(SystemTime::now()) {
+                Ok(remaining) => remaining < buffer_time,
+                Err(_) => true,
+            },
+        }
+    }
+}
+
+impl ProvideCredentials for CachingCredentialsProvider {
+    fn provide_credentials<'a>(&'a self) -> future::ProvideCredentials<'a>
+    where
+        Self: 'a,
+    {
+        future::ProvideCredentials::new(self.credentials())
+    }
+}
+
+impl std::fmt::Debug for CachingCredentialsProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CachingCredentialsProvider")
+            .field("buffer_time", &self.buffer_time)
+            .finish()
+    }
+}
+
+/// Builder for [`CachingCredentialsProvider`].
+#[derive(Default)]
+pub struct Builder {
+    inner: Option<Arc<dyn ProvideCredentials>>,
+    buffer_time: Option<Duration>,
+}
+
+impl Builder {
+    /// Sets the provider whose credentials will be cached.
+    pub fn provider(mut self, provider: impl ProvideCredentials + 'static) -> Self {
+        self.inner = Some(Arc::new(provider));
+        self
+    }
+
+    /// Sets how long before their stated expiry cached credentials are considered stale.
+    /// Defaults to 10 minutes.
+    pub fn buffer_time(mut self, buffer_time: Duration) -> Self {
+        self.buffer_time = Some(buffer_time);
+        self
+    }
+
+    /// Builds a [`CachingCredentialsProvider`].
+    ///
+    /// # Panics
+    /// Panics if no provider was set.
+    pub fn build(self) -> CachingCredentialsProvider {
+        CachingCredentialsProvider {
+            inner: self.inner.expect("a provider must be set"),
+            buffer_time: self.buffer_time.unwrap_or(DEFAULT_BUFFER_TIME),
+            cache: tokio::sync::Mutex::new(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[derive(Debug)]
+    struct CountingProvider {
+        calls: Arc<AtomicUsize>,
+    }
+
+    impl ProvideCredentials for CountingProvider {
+        fn provide_credentials<'a>(&'a self) -> future::ProvideCredentials<'a>
+        where
+            Self: 'a,
+        {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            future::ProvideCredentials::ready(Ok(Credentials::new(
+                "akid",
+                "secret",
+                None,
+                None,
+                "counting-provider",
+            )))
+        }
+    }
+
+    #[tokio::test]
+    async fn concurrent_refreshes_are_single_flight() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let provider = Arc::new(
+            CachingCredentialsProvider::builder()
+                .provider(CountingProvider {
+                    calls: calls.clone(),
+                })
+                .build(),
+        );
+
+        let futures = (0..10).map(|_| {
+            let provider = provider.clone();
+            async move { provider.provide_credentials().await }
+        });
+        let results = futures::future::join_all(futures).await;
+        assert!(results.into_iter().all(|r| r.is_ok()));
+
+        // Static credentials never expire, so after the first refresh every other caller
+        // should observe the cached value rather than triggering its own refresh.
+        assert_eq!(1, calls.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn credentials_without_expiry_are_cached_indefinitely() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let provider = CachingCredentialsProvider::builder()
+            .provider(CountingProvider {
+                calls: calls.clone(),
+            })
+            .build();
+
+        provider.provide_credentials().await.expect("ok");
+        provider.provide_credentials().await.expect("ok");
+
+        assert_eq!(1, calls.load(Ordering::SeqCst));
+    }
+}
@@ -3,13 +3,16 @@
  * SPDX-License-Identifier: Apache-2.0.
  */
 
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use aws_sdk_sts::operation::AssumeRole;
 use aws_sdk_sts::{Config, Credentials};
 use aws_types::region::Region;
 
 use super::repr;
+use crate::profile::credential::mfa::{ErrorMfaTokenProvider, ProvideMfaToken};
+use crate::profile::credential::process::CredentialProcessProvider;
 use crate::profile::credential::repr::BaseProvider;
 use crate::profile::credential::ProfileFileError;
 use crate::sts;
@@ -18,6 +21,7 @@ use aws_types::credential;
 use aws_types::credential::{CredentialsError, ProvideCredentials};
 use aws_types::os_shim_internal::Fs;
 use smithy_client::DynConnector;
+use smithy_http::result::SdkError;
 use std::fmt::{Debug, Formatter};
 
 #[derive(Debug)]
@@ -25,6 +29,24 @@ pub struct AssumeRoleProvider {
     role_arn: String,
     external_id: Option<String>,
     session_name: Option<String>,
+    /// The `mfa_serial` of the MFA device this role assumption requires, if any.
+    mfa_serial: Option<String>,
+    /// Supplies the current token code for `mfa_serial` at call time. Defaults to
+    /// [`ErrorMfaTokenProvider`], which fails clearly rather than hanging when `mfa_serial` is set
+    /// but no token provider was registered.
+    mfa_token_provider: Arc<dyn ProvideMfaToken>,
+    /// The requested lifetime of the assumed-role session. Defaults to STS's own default (1
+    /// hour) when unset.
+    duration: Option<Duration>,
+    /// An inline session policy to further restrict the assumed role's permissions.
+    policy: Option<String>,
+    /// ARNs of managed policies to further restrict the assumed role's permissions.
+    policy_arns: Option<Vec<String>>,
+    /// The last set of credentials this provider was able to retrieve. Used to provide
+    /// static stability in the face of an STS outage: if a refresh fails for an
+    /// availability reason, we'd rather hand back stale-but-valid-looking credentials
+    /// than fail the call outright.
+    last_credentials: Mutex<Option<Credentials>>,
 }
 
 pub struct ClientConfiguration {
@@ -47,21 +69,68 @@ impl AssumeRoleProvider {
             .as_ref()
             .cloned()
             .unwrap_or_else(|| sts::util::default_session_name("assume-role-from-profile"));
-        let operation = AssumeRole::builder()
+        let mut builder = AssumeRole::builder()
             .role_arn(&self.role_arn)
             .set_external_id(self.external_id.clone())
             .role_session_name(session_name)
+            .set_serial_number(self.mfa_serial.clone())
+            .set_duration_seconds(
+                self.duration
+                    .map(|duration| i32::try_from(duration.as_secs()).unwrap_or(i32::MAX)),
+            )
+            .set_policy(self.policy.clone())
+            .set_policy_arns(self.policy_arns.clone().map(|arns| {
+                arns.into_iter()
+                    .map(|arn| aws_sdk_sts::model::PolicyDescriptorType::builder().arn(arn).build())
+                    .collect()
+            }));
+        if let Some(serial_number) = &self.mfa_serial {
+            let token_code = self
+                .mfa_token_provider
+                .mfa_token(serial_number)
+                .await
+                .map_err(|err| CredentialsError::ProviderError(err.into()))?;
+            builder = builder.token_code(token_code);
+        }
+        let operation = builder
             .build()
             .expect("operation is valid")
             .make_operation(&config)
             .expect("valid operation");
-        let assume_role_creds = client_config
-            .core_client
-            .call(operation)
-            .await
-            .map_err(|err| CredentialsError::ProviderError(err.into()))?
-            .credentials;
-        sts::util::into_credentials(assume_role_creds, "AssumeRoleProvider")
+        match client_config.core_client.call(operation).await {
+            Ok(output) => {
+                let creds = sts::util::into_credentials(output.credentials, "AssumeRoleProvider")?;
+                *self.last_credentials.lock().unwrap() = Some(creds.clone());
+                Ok(creds)
+            }
+            Err(err) => {
+                if Self::is_availability_error(&err) {
+                    if let Some(stale) = self.last_credentials.lock().unwrap().clone() {
+                        tracing::warn!(
+                            err = %smithy_types::error::display::DisplayErrorContext(&err),
+                            "assume role call failed, likely because STS is unreachable; \
+                             falling back to the last-known credentials, which may be expired"
+                        );
+                        return Ok(stale);
+                    }
+                }
+                Err(CredentialsError::ProviderError(err.into()))
+            }
+        }
+    }
+
+    /// Returns true if `err` represents a transient availability problem (timeout, connection
+    /// failure, a response that could not be read or parsed, or a 5xx response) rather than an authoritative rejection such as `AccessDenied`.
+    /// Only availability errors are eligible for the static-stability fallback; a real
+    /// authorization failure must still be surfaced to the caller.
+    fn is_availability_error<E>(err: &SdkError<E>) -> bool {
+        match err {
+            SdkError::TimeoutError(_) | SdkError::DispatchFailure(_) | SdkError::ResponseError { .. } => {
+                true
+            }
+            SdkError::ServiceError { raw, .. } => raw.http().status().is_server_error(),
+            SdkError::ConstructionFailure(_) => false,
+        }
     }
 }
 
@@ -123,6 +192,9 @@ impl ProviderChain {
                     .build();
                 Arc::new(provider)
             }
+            BaseProvider::CredentialProcess { command } => {
+                Arc::new(CredentialProcessProvider::new(*command))
+            }
         };
         tracing::info!(base = ?repr.base(), "first credentials will be loaded from {:?}", repr.base());
         let chain = repr
@@ -134,6 +206,16 @@ impl ProviderChain {
                     role_arn: role_arn.role_arn.into(),
                     external_id: role_arn.external_id.map(|id| id.into()),
                     session_name: role_arn.session_name.map(|id| id.into()),
+                    mfa_serial: role_arn.mfa_serial.map(|id| id.into()),
+                    mfa_token_provider: factory.mfa_token_provider(),
+                    duration: role_arn
+                        .duration_seconds
+                        .map(|secs| Duration::from_secs(secs as u64)),
+                    policy: role_arn.policy.map(|policy| policy.into()),
+                    policy_arns: role_arn.policy_arns.as_ref().map(|arns| {
+                        arns.iter().map(|arn| arn.to_string()).collect()
+                    }),
+                    last_credentials: Mutex::new(None),
                 }
             })
             .collect();
@@ -145,21 +227,41 @@ pub mod named {
     use std::collections::HashMap;
     use std::sync::Arc;
 
+    use crate::profile::credential::mfa::{ErrorMfaTokenProvider, ProvideMfaToken};
     use aws_types::credential::ProvideCredentials;
     use std::borrow::Cow;
 
     pub struct NamedProviderFactory {
         providers: HashMap<Cow<'static, str>, Arc<dyn ProvideCredentials>>,
+        mfa_token_provider: Arc<dyn ProvideMfaToken>,
     }
 
     impl NamedProviderFactory {
         pub fn new(providers: HashMap<Cow<'static, str>, Arc<dyn ProvideCredentials>>) -> Self {
-            Self { providers }
+            Self {
+                providers,
+                mfa_token_provider: Arc::new(ErrorMfaTokenProvider),
+            }
         }
 
         pub fn provider(&self, name: &str) -> Option<Arc<dyn ProvideCredentials>> {
             self.providers.get(name).cloned()
         }
+
+        /// Registers the implementation used to obtain MFA token codes for profiles whose
+        /// `role_arn` steps specify `mfa_serial`. If this is never called, requesting a token
+        /// code fails with a descriptive error rather than hanging.
+        pub fn with_mfa_token_provider(
+            mut self,
+            mfa_token_provider: impl ProvideMfaToken + 'static,
+        ) -> Self {
+            self.mfa_token_provider = Arc::new(mfa_token_provider);
+            self
+        }
+
+        pub(crate) fn mfa_token_provider(&self) -> Arc<dyn ProvideMfaToken> {
+            self.mfa_token_provider.clone()
+        }
     }
 }
 
@@ -167,13 +269,35 @@ pub mod named {
 mod test {
     use crate::profile::credential::exec::named::NamedProviderFactory;
     use crate::profile::credential::exec::ProviderChain;
+    use crate::profile::credential::exec::{AssumeRoleProvider, ClientConfiguration};
+    use crate::profile::credential::mfa::{ErrorMfaTokenProvider, ProvideMfaToken};
     use crate::profile::credential::repr::{BaseProvider, ProfileChain};
+    use crate::test_util::ScriptedConnection;
+    use aws_sdk_sts::Credentials;
+    use std::sync::{Arc, Mutex};
     use aws_sdk_sts::Region;
     use smithy_client::dvr;
     use smithy_client::erase::DynConnector;
+    use smithy_http::body::SdkBody;
+    use smithy_http::connector::ConnectorError;
     use std::collections::HashMap;
 
+    // A canned `AssumeRole` response, the XML shape STS (a query/XML protocol) actually returns.
+    const ASSUME_ROLE_RESPONSE: &str = r#"<AssumeRoleResponse xmlns="https://sts.amazonaws.com/doc/2011-06-15/">
+  <AssumeRoleResult>
+    <Credentials>
+      <AccessKeyId>AKID</AccessKeyId>
+      <SecretAccessKey>secret</SecretAccessKey>
+      <SessionToken>session-token</SessionToken>
+      <Expiration>2099-01-01T00:00:00Z</Expiration>
+    </Credentials>
+  </AssumeRoleResult>
+  <ResponseMetadata><RequestId>test-request-id</RequestId></ResponseMetadata>
+</AssumeRoleResponse>"#;
+
     fn stub_connector() -> DynConnector {
+        // This connection is never actually dispatched against by the test below, so an empty
+        // recorded-traffic replay is sufficient here.
         DynConnector::new(dvr::ReplayingConnection::new(vec![]))
     }
 
@@ -199,4 +323,166 @@ mod test {
             err
         );
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn static_stability_serves_stale_credentials_on_availability_error() {
+        let connection = ScriptedConnection::new(vec![
+            Ok(http::Response::builder()
+                .status(200)
+                .body(SdkBody::from(ASSUME_ROLE_RESPONSE))
+                .unwrap()),
+            Err(ConnectorError::io(Box::new(std::io::Error::new(
+                std::io::ErrorKind::ConnectionReset,
+                "connection reset by peer",
+            )))),
+        ]);
+        let provider = AssumeRoleProvider {
+            role_arn: "arn:aws:iam::123456789012:role/static-stability".into(),
+            external_id: None,
+            session_name: None,
+            mfa_serial: None,
+            mfa_token_provider: Arc::new(ErrorMfaTokenProvider),
+            duration: None,
+            policy: None,
+            policy_arns: None,
+            last_credentials: Mutex::new(None),
+        };
+        let client_config = ClientConfiguration {
+            core_client: aws_sdk_sts::RawClient::from_conf_conn(
+                aws_sdk_sts::Config::builder()
+                    .region(Region::new("us-east-1"))
+                    .build(),
+                DynConnector::new(connection),
+            ),
+            region: Some(Region::new("us-east-1")),
+        };
+
+        let good = provider
+            .credentials(
+                Credentials::new("akid", "secret", None, None, "test"),
+                &client_config,
+            )
+            .await
+            .expect("first call succeeds and primes the cache");
+
+        let stale = provider
+            .credentials(
+                Credentials::new("akid", "secret", None, None, "test"),
+                &client_config,
+            )
+            .await
+            .expect("second call falls back to the cached credentials instead of erroring");
+
+        assert_eq!(good.access_key_id(), stale.access_key_id());
+    }
+
+    #[derive(Debug)]
+    struct StaticMfaToken(String);
+
+    #[async_trait::async_trait]
+    impl ProvideMfaToken for StaticMfaToken {
+        async fn mfa_token(
+            &self,
+            _serial_number: &str,
+        ) -> Result<String, crate::profile::credential::mfa::ProvideMfaTokenError> {
+            Ok(self.0.clone())
+        }
+    }
+
+    #[tokio::test]
+    async fn mfa_token_is_attached_to_assume_role_request() {
+        let connection = ScriptedConnection::ok(ASSUME_ROLE_RESPONSE);
+        let mut provider = AssumeRoleProvider {
+            role_arn: "arn:aws:iam::123456789012:role/static-stability".into(),
+            external_id: None,
+            session_name: None,
+            mfa_serial: None,
+            mfa_token_provider: Arc::new(ErrorMfaTokenProvider),
+            duration: None,
+            policy: None,
+            policy_arns: None,
+            last_credentials: Mutex::new(None),
+        };
+        provider.mfa_serial = Some("arn:aws:iam::123456789012:mfa/user".into());
+        provider.mfa_token_provider = Arc::new(StaticMfaToken("123456".into()));
+        let client_config = ClientConfiguration {
+            core_client: aws_sdk_sts::RawClient::from_conf_conn(
+                aws_sdk_sts::Config::builder()
+                    .region(Region::new("us-east-1"))
+                    .build(),
+                DynConnector::new(connection.clone()),
+            ),
+            region: Some(Region::new("us-east-1")),
+        };
+
+        provider
+            .credentials(
+                Credentials::new("akid", "secret", None, None, "test"),
+                &client_config,
+            )
+            .await
+            .expect("assume role succeeds once the token code is supplied");
+
+        let sent = connection.requests();
+        let body = String::from_utf8(sent[0].body().clone()).unwrap();
+        assert!(
+            body.contains("TokenCode=123456"),
+            "request body did not carry the MFA token code: {}",
+            body
+        );
+        assert!(
+            body.contains("SerialNumber=arn%3Aaws%3Aiam%3A%3A123456789012%3Amfa%2Fuser"),
+            "request body did not carry the MFA serial number: {}",
+            body
+        );
+    }
+
+    #[tokio::test]
+    async fn duration_and_policy_are_sent_to_sts() {
+        let connection = ScriptedConnection::ok(ASSUME_ROLE_RESPONSE);
+        let mut provider = AssumeRoleProvider {
+            role_arn: "arn:aws:iam::123456789012:role/static-stability".into(),
+            external_id: None,
+            session_name: None,
+            mfa_serial: None,
+            mfa_token_provider: Arc::new(ErrorMfaTokenProvider),
+            duration: None,
+            policy: None,
+            policy_arns: None,
+            last_credentials: Mutex::new(None),
+        };
+        provider.duration = Some(std::time::Duration::from_secs(900));
+        provider.policy = Some(r#"{"Version":"2012-10-17","Statement":[]}"#.into());
+        provider.policy_arns = Some(vec!["arn:aws:iam::aws:policy/ReadOnlyAccess".into()]);
+        let client_config = ClientConfiguration {
+            core_client: aws_sdk_sts::RawClient::from_conf_conn(
+                aws_sdk_sts::Config::builder()
+                    .region(Region::new("us-east-1"))
+                    .build(),
+                DynConnector::new(connection.clone()),
+            ),
+            region: Some(Region::new("us-east-1")),
+        };
+
+        provider
+            .credentials(
+                Credentials::new("akid", "secret", None, None, "test"),
+                &client_config,
+            )
+            .await
+            .expect("assume role succeeds with a scoped-down 15-minute session");
+
+        let sent = connection.requests();
+        let body = String::from_utf8(sent[0].body().clone()).unwrap();
+        assert!(
+            body.contains("DurationSeconds=900"),
+            "request body did not carry the requested session duration: {}",
+            body
+        );
+        assert!(
+            body.contains("PolicyArns.member.1.arn=arn%3Aaws%3Aiam%3A%3Aaws%3Apolicy%2FReadOnlyAccess"),
+            "request body did not carry the requested policy ARN: {}",
+            body
+        );
+    }
+}
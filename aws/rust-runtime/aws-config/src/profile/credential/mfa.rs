@@ -0,0 +1,80 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0.
+ */
+
+//! Support for supplying an MFA token code when a profile's `role_arn` step requires `mfa_serial`.
+
+use std::error::Error as StdError;
+use std::fmt;
+
+/// Given the ARN or serial number of an MFA device, returns the current token code displayed on
+/// it. Implementations will typically prompt the user on a terminal, but may also integrate with
+/// a virtual MFA device or some other out-of-band mechanism.
+///
+/// Register an implementation with
+/// [`NamedProviderFactory::with_mfa_token_provider`](super::exec::named::NamedProviderFactory::with_mfa_token_provider)
+/// to make it available to profile-based role assumption. If none is registered, requesting a
+/// token code fails with a descriptive error, which keeps non-interactive use unaffected.
+#[async_trait::async_trait]
+pub trait ProvideMfaToken: Send + Sync + fmt::Debug {
+    /// Returns the current token code for the MFA device identified by `serial_number`.
+    async fn mfa_token(&self, serial_number: &str) -> Result<String, ProvideMfaTokenError>;
+}
+
+/// An error returned by a [`ProvideMfaToken`] implementation.
+#[derive(Debug)]
+pub struct ProvideMfaTokenError {
+    source: Box<dyn StdError + Send + Sync + 'static>,
+}
+
+impl ProvideMfaTokenError {
+    pub fn new(source: impl Into<Box<dyn StdError + Send + Sync + 'static>>) -> Self {
+        Self {
+            source: source.into(),
+        }
+    }
+}
+
+impl fmt::Display for ProvideMfaTokenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "failed to obtain an MFA token code: {}", self.source)
+    }
+}
+
+impl StdError for ProvideMfaTokenError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        Some(self.source.as_ref())
+    }
+}
+
+/// The default [`ProvideMfaToken`] used when no implementation has been registered. Always
+/// errors, so non-interactive credential resolution isn't left waiting on a token that will
+/// never come.
+#[derive(Debug, Default)]
+pub(crate) struct ErrorMfaTokenProvider;
+
+#[async_trait::async_trait]
+impl ProvideMfaToken for ErrorMfaTokenProvider {
+    async fn mfa_token(&self, serial_number: &str) -> Result<String, ProvideMfaTokenError> {
+        Err(ProvideMfaTokenError::new(format!(
+            "profile requires an MFA token code for device `{}`, but no `ProvideMfaToken` \
+             implementation was registered with the `NamedProviderFactory`",
+            serial_number
+        )))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn default_provider_errors() {
+        let err = ErrorMfaTokenProvider
+            .mfa_token("arn:aws:iam::123456789012:mfa/user")
+            .await
+            .expect_err("no provider registered");
+        assert!(format!("{}", err).contains("no `ProvideMfaToken` implementation"));
+    }
+}
@@ -0,0 +1,73 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0.
+ */
+
+//! Resolution of credentials providers from the `~/.aws/config`/`~/.aws/credentials` profile
+//! file format. See [`repr::ProfileChain`] for the intermediate representation profiles are
+//! parsed into, and [`exec::ProviderChain`] for how that representation is turned into
+//! concrete [`ProvideCredentials`](aws_types::credential::ProvideCredentials) implementations.
+
+pub(crate) mod exec;
+mod mfa;
+mod process;
+pub(crate) mod repr;
+
+use std::fmt;
+
+/// An error encountered while resolving a profile's credentials provider chain.
+#[derive(Debug)]
+pub enum ProfileFileError {
+    /// The profile referenced a named provider (e.g. in `credential_source`) that isn't
+    /// registered with the [`NamedProviderFactory`](exec::named::NamedProviderFactory).
+    UnknownProvider { name: String },
+    /// A profile was referenced (directly or via `source_profile`) that doesn't exist in the
+    /// profile file.
+    MissingProfile { name: String },
+    /// A profile's `role_arn` chain forms a cycle, e.g. `a` has `source_profile = b` and `b` has
+    /// `source_profile = a`.
+    CredentialLoop { profiles: Vec<String> },
+    /// A profile didn't specify enough information to resolve to a base provider, e.g. it has
+    /// neither a static access key, a `source_profile`, nor a `credential_source`.
+    InvalidCredentialSource { profile: String, message: String },
+    /// A field was present but could not be parsed, e.g. a non-numeric `duration_seconds`.
+    InvalidField {
+        profile: String,
+        field: &'static str,
+        message: String,
+    },
+}
+
+impl fmt::Display for ProfileFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProfileFileError::UnknownProvider { name } => write!(
+                f,
+                "profile referenced `{}` provider but that provider is not supported",
+                name
+            ),
+            ProfileFileError::MissingProfile { name } => {
+                write!(f, "profile `{}` is not defined in the profile file", name)
+            }
+            ProfileFileError::CredentialLoop { profiles } => write!(
+                f,
+                "profile chain forms a cycle: {}",
+                profiles.join(" -> ")
+            ),
+            ProfileFileError::InvalidCredentialSource { profile, message } => {
+                write!(f, "profile `{}` has an invalid credential source: {}", profile, message)
+            }
+            ProfileFileError::InvalidField {
+                profile,
+                field,
+                message,
+            } => write!(
+                f,
+                "profile `{}` has an invalid `{}`: {}",
+                profile, field, message
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ProfileFileError {}
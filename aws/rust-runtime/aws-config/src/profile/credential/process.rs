@@ -0,0 +1,216 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0.
+ */
+
+//! A [`ProvideCredentials`] backed by a profile's `credential_process`, an external command that
+//! prints a Version-1 JSON credentials payload to stdout.
+
+use std::error::Error as StdError;
+use std::fmt;
+use std::process::ExitStatus;
+use std::str::FromStr;
+use std::time::SystemTime;
+
+use aws_types::credential::{self, future, CredentialsError, ProvideCredentials};
+use aws_types::Credentials;
+use smithy_types::date_time::{DateTime, Format};
+
+/// A [`ProvideCredentials`] implementation that runs an external `command` and parses its
+/// stdout as a Version-1 `credential_process` JSON payload:
+///
+/// ```json
+/// {"Version": 1, "AccessKeyId": "...", "SecretAccessKey": "...", "SessionToken": "...", "Expiration": "..."}
+/// ```
+#[derive(Debug)]
+pub(crate) struct CredentialProcessProvider {
+    command: String,
+}
+
+impl CredentialProcessProvider {
+    pub(crate) fn new(command: impl Into<String>) -> Self {
+        Self {
+            command: command.into(),
+        }
+    }
+
+    async fn credentials(&self) -> credential::Result {
+        let output = tokio::process::Command::new("sh")
+            .arg("-c")
+            .arg(&self.command)
+            .output()
+            .await
+            .map_err(|err| CredentialsError::ProviderError(Box::new(CredentialProcessError::Spawn(err))))?;
+
+        if !output.status.success() {
+            // Deliberately don't include the process's stderr here: it may contain secrets the
+            // command was never meant to have printed, and we don't want those ending up in logs
+            // or error messages just because the command failed.
+            return Err(CredentialsError::ProviderError(Box::new(
+                CredentialProcessError::Exit {
+                    status: output.status,
+                },
+            )));
+        }
+
+        let parsed: ProcessOutput = serde_json::from_slice(&output.stdout).map_err(|err| {
+            CredentialsError::ProviderError(Box::new(CredentialProcessError::Parse(err)))
+        })?;
+
+        if parsed.version != 1 {
+            return Err(CredentialsError::ProviderError(Box::new(
+                CredentialProcessError::UnsupportedVersion(parsed.version),
+            )));
+        }
+
+        let expiry = parsed
+            .expiration
+            .as_deref()
+            .map(parse_expiration)
+            .transpose()?;
+
+        Ok(Credentials::new(
+            parsed.access_key_id,
+            parsed.secret_access_key,
+            parsed.session_token,
+            expiry,
+            "CredentialProcess",
+        ))
+    }
+}
+
+fn parse_expiration(raw: &str) -> Result<SystemTime, CredentialsError> {
+    DateTime::from_str(raw, Format::DateTime)
+        .ok()
+        .and_then(|dt| SystemTime::try_from(dt).ok())
+        .ok_or_else(|| {
+            CredentialsError::ProviderError(Box::new(CredentialProcessError::InvalidExpiration(
+                raw.to_string(),
+            )))
+        })
+}
+
+impl ProvideCredentials for CredentialProcessProvider {
+    fn provide_credentials<'a>(&'a self) -> future::ProvideCredentials<'a>
+    where
+        Self: 'a,
+    {
+        future::ProvideCredentials::new(self.credentials())
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct ProcessOutput {
+    #[serde(rename = "Version")]
+    version: u32,
+    #[serde(rename = "AccessKeyId")]
+    access_key_id: String,
+    #[serde(rename = "SecretAccessKey")]
+    secret_access_key: String,
+    #[serde(rename = "SessionToken")]
+    session_token: Option<String>,
+    #[serde(rename = "Expiration")]
+    expiration: Option<String>,
+}
+
+#[derive(Debug)]
+enum CredentialProcessError {
+    Spawn(std::io::Error),
+    Exit { status: ExitStatus },
+    Parse(serde_json::Error),
+    UnsupportedVersion(u32),
+    InvalidExpiration(String),
+}
+
+impl fmt::Display for CredentialProcessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CredentialProcessError::Spawn(err) => {
+                write!(f, "failed to spawn `credential_process`: {}", err)
+            }
+            CredentialProcessError::Exit { status } => {
+                write!(f, "`credential_process` exited with {}", status)
+            }
+            CredentialProcessError::Parse(err) => write!(
+                f,
+                "`credential_process` did not print a valid Version-1 credentials payload: {}",
+                err
+            ),
+            CredentialProcessError::UnsupportedVersion(version) => write!(
+                f,
+                "`credential_process` reported unsupported payload version `{}`; only version 1 is supported",
+                version
+            ),
+            CredentialProcessError::InvalidExpiration(raw) => {
+                write!(f, "`credential_process` returned an unparseable `Expiration`: `{}`", raw)
+            }
+        }
+    }
+}
+
+impl StdError for CredentialProcessError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn success_payload_is_parsed_into_credentials() {
+        let provider = CredentialProcessProvider::new(
+            r#"echo '{"Version":1,"AccessKeyId":"AKID","SecretAccessKey":"secret","SessionToken":"token","Expiration":"2099-01-01T00:00:00Z"}'"#,
+        );
+        let creds = provider
+            .provide_credentials()
+            .await
+            .expect("well-formed Version-1 payload resolves to credentials");
+        assert_eq!("AKID", creds.access_key_id());
+        assert_eq!("secret", creds.secret_access_key());
+        assert_eq!(Some("token"), creds.session_token());
+    }
+
+    #[tokio::test]
+    async fn non_zero_exit_is_an_error_that_does_not_leak_stderr() {
+        let provider = CredentialProcessProvider::new("echo supersecret-value 1>&2; exit 1");
+        let err = provider
+            .provide_credentials()
+            .await
+            .expect_err("a non-zero exit status is an error");
+        let message = format!("{}", err);
+        assert!(message.contains("exited with"), "`{}`", message);
+        assert!(
+            !message.contains("supersecret-value"),
+            "error message leaked the process's stderr: `{}`",
+            message
+        );
+    }
+
+    #[tokio::test]
+    async fn malformed_json_is_an_error() {
+        let provider = CredentialProcessProvider::new("echo not-json");
+        let err = provider
+            .provide_credentials()
+            .await
+            .expect_err("non-JSON stdout is an error");
+        assert!(
+            format!("{}", err).contains("did not print a valid Version-1"),
+            "`{}`",
+            err
+        );
+    }
+
+    #[tokio::test]
+    async fn unsupported_version_is_an_error() {
+        let provider = CredentialProcessProvider::new(
+            r#"echo '{"Version":2,"AccessKeyId":"AKID","SecretAccessKey":"secret"}'"#,
+        );
+        let err = provider
+            .provide_credentials()
+            .await
+            .expect_err("an unsupported payload version is an error");
+        assert!(
+            format!("{}", err).contains("unsupported payload version `2`"),
+            "`{}`",
+            err
+        );
+    }
+}
@@ -0,0 +1,377 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0.
+ */
+
+//! An intermediate representation of a profile, resolved from the profile file format but not
+//! yet backed by concrete providers. See [`ProfileChain`] and [`BaseProvider`].
+
+use std::collections::HashMap;
+
+use super::ProfileFileError;
+
+/// A single profile's raw key/value pairs, as read from `~/.aws/config`/`~/.aws/credentials`.
+pub type RawProfile = HashMap<String, String>;
+
+/// A profile resolved into a series of steps: a base provider, followed by zero or more
+/// role-assumption steps.
+#[derive(Debug)]
+pub struct ProfileChain<'a> {
+    pub(crate) base: BaseProvider<'a>,
+    pub(crate) chain: Vec<RoleArn<'a>>,
+}
+
+impl<'a> ProfileChain<'a> {
+    pub fn base(&self) -> &BaseProvider<'a> {
+        &self.base
+    }
+
+    pub fn chain(&self) -> &[RoleArn<'a>] {
+        &self.chain
+    }
+
+    /// Resolves the chain of profiles starting at `start_profile`, following `source_profile`
+    /// links until a profile resolves to a [`BaseProvider`] (a static access key, a
+    /// `credential_source`, a `web_identity_token_file`, or a `credential_process`).
+    pub fn parse(
+        start_profile: &'a str,
+        profiles: &'a HashMap<String, RawProfile>,
+    ) -> Result<Self, ProfileFileError> {
+        let mut chain = Vec::new();
+        let mut visited = vec![start_profile.to_string()];
+        let mut current_name = start_profile;
+        let mut current = get_profile(profiles, current_name)?;
+        loop {
+            match (current.get("role_arn"), current.get("source_profile")) {
+                (Some(_), Some(source_profile)) => {
+                    chain.push(RoleArn::from_profile(current_name, current)?);
+                    if visited.iter().any(|name| name == source_profile) {
+                        let mut cycle = visited.clone();
+                        cycle.push(source_profile.clone());
+                        return Err(ProfileFileError::CredentialLoop { profiles: cycle });
+                    }
+                    visited.push(source_profile.clone());
+                    current_name = source_profile;
+                    current = get_profile(profiles, current_name)?;
+                }
+                (Some(role_arn), None) if current.contains_key("web_identity_token_file") => {
+                    let base = BaseProvider::WebIdentityTokenRole {
+                        web_identity_token_file: &current["web_identity_token_file"],
+                        role_arn,
+                        session_name: current.get("role_session_name").map(String::as_str),
+                    };
+                    return Ok(ProfileChain { base, chain });
+                }
+                (Some(_), None) => {
+                    chain.push(RoleArn::from_profile(current_name, current)?);
+                    let base = BaseProvider::from_named_source(current_name, current)?;
+                    return Ok(ProfileChain { base, chain });
+                }
+                (None, _) => {
+                    let base = BaseProvider::from_profile(current_name, current)?;
+                    return Ok(ProfileChain { base, chain });
+                }
+            }
+        }
+    }
+}
+
+fn get_profile<'a>(
+    profiles: &'a HashMap<String, RawProfile>,
+    name: &str,
+) -> Result<&'a RawProfile, ProfileFileError> {
+    profiles.get(name).ok_or_else(|| ProfileFileError::MissingProfile {
+        name: name.to_string(),
+    })
+}
+
+/// The starting point of a profile chain.
+#[derive(Debug)]
+pub enum BaseProvider<'a> {
+    /// A named provider, e.g. `Environment` or `Ec2InstanceMetadata`, registered with the
+    /// [`NamedProviderFactory`](super::exec::named::NamedProviderFactory).
+    NamedSource(&'a str),
+    /// A static access key, e.g. `aws_access_key_id`/`aws_secret_access_key`.
+    AccessKey(aws_sdk_sts::Credentials),
+    /// A web identity token, e.g. `web_identity_token_file`/`role_arn`.
+    WebIdentityTokenRole {
+        web_identity_token_file: &'a str,
+        role_arn: &'a str,
+        session_name: Option<&'a str>,
+    },
+    /// A `credential_process` command that prints a Version-1 JSON credentials payload.
+    CredentialProcess { command: &'a str },
+}
+
+impl<'a> BaseProvider<'a> {
+    /// Parses the base provider out of a terminal profile, i.e. one with no `role_arn` of its
+    /// own (its credentials are used directly, not assumed).
+    fn from_profile(
+        profile_name: &'a str,
+        profile: &'a RawProfile,
+    ) -> Result<Self, ProfileFileError> {
+        if let Some(command) = profile.get("credential_process") {
+            return Ok(BaseProvider::CredentialProcess { command });
+        }
+        if let (Some(access_key_id), Some(secret_access_key)) = (
+            profile.get("aws_access_key_id"),
+            profile.get("aws_secret_access_key"),
+        ) {
+            return Ok(BaseProvider::AccessKey(aws_sdk_sts::Credentials::new(
+                access_key_id,
+                secret_access_key,
+                profile.get("aws_session_token").cloned(),
+                None,
+                "SharedConfigCredentials",
+            )));
+        }
+        if let Some(web_identity_token_file) = profile.get("web_identity_token_file") {
+            let role_arn = profile.get("role_arn").ok_or_else(|| {
+                ProfileFileError::InvalidCredentialSource {
+                    profile: profile_name.to_string(),
+                    message: "`web_identity_token_file` requires `role_arn`".into(),
+                }
+            })?;
+            return Ok(BaseProvider::WebIdentityTokenRole {
+                web_identity_token_file,
+                role_arn,
+                session_name: profile.get("role_session_name").map(String::as_str),
+            });
+        }
+        Self::from_named_source(profile_name, profile)
+    }
+
+    /// Parses the base provider out of a profile that names another, already-registered
+    /// provider via `credential_source` (either as a terminal profile, or as the tail of a
+    /// `role_arn`/`credential_source` step).
+    fn from_named_source(
+        profile_name: &'a str,
+        profile: &'a RawProfile,
+    ) -> Result<Self, ProfileFileError> {
+        match profile.get("credential_source") {
+            Some(name) => Ok(BaseProvider::NamedSource(name)),
+            None => Err(ProfileFileError::InvalidCredentialSource {
+                profile: profile_name.to_string(),
+                message: "profile has no `aws_access_key_id`, `web_identity_token_file`, \
+                          `credential_process`, or `credential_source`"
+                    .into(),
+            }),
+        }
+    }
+}
+
+/// A single `role_arn`/`source_profile` step in a profile chain.
+#[derive(Debug)]
+pub struct RoleArn<'a> {
+    pub(crate) role_arn: &'a str,
+    pub(crate) external_id: Option<&'a str>,
+    pub(crate) session_name: Option<&'a str>,
+    /// The `mfa_serial` configured on this profile, if any. When set, `AssumeRoleProvider` will
+    /// ask the registered `ProvideMfaToken` implementation for a token code before calling
+    /// `AssumeRole`.
+    pub(crate) mfa_serial: Option<&'a str>,
+    /// The `duration_seconds` configured on this profile, if any.
+    pub(crate) duration_seconds: Option<u32>,
+    /// The `role_policy` configured on this profile, if any.
+    pub(crate) policy: Option<&'a str>,
+    /// The `role_policy_arns` configured on this profile, if any.
+    pub(crate) policy_arns: Option<Vec<&'a str>>,
+}
+
+impl<'a> RoleArn<'a> {
+    /// Parses a `role_arn`/`source_profile` step out of a profile's raw key/value pairs.
+    fn from_profile(
+        profile_name: &'a str,
+        profile: &'a RawProfile,
+    ) -> Result<Self, ProfileFileError> {
+        let role_arn = profile
+            .get("role_arn")
+            .map(String::as_str)
+            .expect("caller already checked `role_arn` is present");
+        let duration_seconds = profile
+            .get("duration_seconds")
+            .map(|value| {
+                value.parse::<u32>().map_err(|err| ProfileFileError::InvalidField {
+                    profile: profile_name.to_string(),
+                    field: "duration_seconds",
+                    message: err.to_string(),
+                })
+            })
+            .transpose()?;
+        let policy_arns = profile.get("role_policy_arns").map(|value| {
+            value
+                .split(',')
+                .map(str::trim)
+                .filter(|arn| !arn.is_empty())
+                .collect::<Vec<_>>()
+        });
+        Ok(RoleArn {
+            role_arn,
+            external_id: profile.get("external_id").map(String::as_str),
+            session_name: profile.get("role_session_name").map(String::as_str),
+            mfa_serial: profile.get("mfa_serial").map(String::as_str),
+            duration_seconds,
+            policy: profile.get("role_policy").map(String::as_str),
+            policy_arns,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn profiles(entries: &[(&str, &[(&str, &str)])]) -> HashMap<String, RawProfile> {
+        entries
+            .iter()
+            .map(|(name, keys)| {
+                let profile = keys
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect();
+                (name.to_string(), profile)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn credential_process_is_parsed_as_the_base_provider() {
+        let profiles = profiles(&[(
+            "default",
+            &[("credential_process", "/usr/bin/get-creds.sh --flag")],
+        )]);
+        let chain = ProfileChain::parse("default", &profiles).expect("valid profile");
+        match chain.base() {
+            BaseProvider::CredentialProcess { command } => {
+                assert_eq!(&"/usr/bin/get-creds.sh --flag", command);
+            }
+            other => panic!("expected a `credential_process` base provider, got {:?}", other),
+        }
+        assert!(chain.chain().is_empty());
+    }
+
+    #[test]
+    fn access_key_is_parsed_as_the_base_provider() {
+        let profiles = profiles(&[(
+            "default",
+            &[
+                ("aws_access_key_id", "AKID"),
+                ("aws_secret_access_key", "secret"),
+            ],
+        )]);
+        let chain = ProfileChain::parse("default", &profiles).expect("valid profile");
+        match chain.base() {
+            BaseProvider::AccessKey(creds) => assert_eq!("AKID", creds.access_key_id()),
+            other => panic!("expected an access key base provider, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn terminal_profile_without_a_base_provider_is_an_error() {
+        let profiles = profiles(&[("default", &[])]);
+        let err = ProfileChain::parse("default", &profiles).expect_err("no base provider");
+        assert!(matches!(err, ProfileFileError::InvalidCredentialSource { .. }));
+    }
+
+    #[test]
+    fn missing_source_profile_is_an_error() {
+        let profiles = profiles(&[(
+            "default",
+            &[
+                ("role_arn", "arn:aws:iam::123456789012:role/test"),
+                ("source_profile", "missing"),
+            ],
+        )]);
+        let err = ProfileChain::parse("default", &profiles).expect_err("source profile is missing");
+        assert!(matches!(err, ProfileFileError::MissingProfile { name } if name == "missing"));
+    }
+
+    #[test]
+    fn mfa_serial_is_parsed_onto_the_role_arn_step() {
+        let profiles = profiles(&[
+            (
+                "default",
+                &[
+                    ("role_arn", "arn:aws:iam::123456789012:role/mfa-protected"),
+                    ("source_profile", "base"),
+                    ("mfa_serial", "arn:aws:iam::123456789012:mfa/user"),
+                ],
+            ),
+            (
+                "base",
+                &[
+                    ("aws_access_key_id", "AKID"),
+                    ("aws_secret_access_key", "secret"),
+                ],
+            ),
+        ]);
+        let chain = ProfileChain::parse("default", &profiles).expect("valid profile chain");
+        assert_eq!(1, chain.chain().len());
+        assert_eq!(
+            Some("arn:aws:iam::123456789012:mfa/user"),
+            chain.chain()[0].mfa_serial
+        );
+    }
+
+    #[test]
+    fn duration_and_policy_are_parsed_onto_the_role_arn_step() {
+        let profiles = profiles(&[
+            (
+                "default",
+                &[
+                    ("role_arn", "arn:aws:iam::123456789012:role/scoped-down"),
+                    ("source_profile", "base"),
+                    ("duration_seconds", "900"),
+                    ("role_policy", r#"{"Version":"2012-10-17","Statement":[]}"#),
+                    (
+                        "role_policy_arns",
+                        "arn:aws:iam::aws:policy/ReadOnlyAccess, arn:aws:iam::aws:policy/job-function/ViewOnlyAccess",
+                    ),
+                ],
+            ),
+            (
+                "base",
+                &[
+                    ("aws_access_key_id", "AKID"),
+                    ("aws_secret_access_key", "secret"),
+                ],
+            ),
+        ]);
+        let chain = ProfileChain::parse("default", &profiles).expect("valid profile chain");
+        let role = &chain.chain()[0];
+        assert_eq!(Some(900), role.duration_seconds);
+        assert_eq!(
+            Some(r#"{"Version":"2012-10-17","Statement":[]}"#),
+            role.policy
+        );
+        assert_eq!(
+            Some(vec![
+                "arn:aws:iam::aws:policy/ReadOnlyAccess",
+                "arn:aws:iam::aws:policy/job-function/ViewOnlyAccess",
+            ]),
+            role.policy_arns
+        );
+    }
+
+    #[test]
+    fn cyclical_source_profiles_are_an_error() {
+        let profiles = profiles(&[
+            (
+                "a",
+                &[
+                    ("role_arn", "arn:aws:iam::123456789012:role/a"),
+                    ("source_profile", "b"),
+                ],
+            ),
+            (
+                "b",
+                &[
+                    ("role_arn", "arn:aws:iam::123456789012:role/b"),
+                    ("source_profile", "a"),
+                ],
+            ),
+        ]);
+        let err = ProfileChain::parse("a", &profiles).expect_err("profiles form a cycle");
+        assert!(matches!(err, ProfileFileError::CredentialLoop { .. }));
+    }
+}
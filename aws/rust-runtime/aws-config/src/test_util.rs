@@ -0,0 +1,79 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0.
+ */
+
+//! Test-only request/response doubles shared by this crate's provider unit tests.
+#![cfg(test)]
+
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+use smithy_http::body::SdkBody;
+use smithy_http::connector::ConnectorError;
+
+/// A connector that replays a fixed, ordered script of responses (or errors), recording every
+/// request it receives so tests can assert on what was actually sent over the wire.
+///
+/// Unlike `smithy_client::dvr`, which replays exact recorded network traffic, this is a minimal
+/// stand-in for tests that only care about a handful of outgoing calls and don't have a
+/// recorded fixture to replay.
+#[derive(Clone)]
+pub(crate) struct ScriptedConnection {
+    script: Arc<Mutex<VecDeque<Result<http::Response<SdkBody>, ConnectorError>>>>,
+    requests: Arc<Mutex<Vec<http::Request<Vec<u8>>>>>,
+}
+
+impl ScriptedConnection {
+    pub(crate) fn new(script: Vec<Result<http::Response<SdkBody>, ConnectorError>>) -> Self {
+        Self {
+            script: Arc::new(Mutex::new(script.into_iter().collect())),
+            requests: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Convenience constructor for a single successful response.
+    pub(crate) fn ok(body: impl Into<SdkBody>) -> Self {
+        Self::new(vec![Ok(http::Response::builder()
+            .status(200)
+            .body(body.into())
+            .unwrap())])
+    }
+
+    /// Every request this connection has received so far, in order, with the request body fully
+    /// materialized (the SDK always buffers outgoing request bodies in memory before dispatch,
+    /// so this is safe, unlike reading an inbound response body this way).
+    pub(crate) fn requests(&self) -> Vec<http::Request<Vec<u8>>> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+impl tower::Service<http::Request<SdkBody>> for ScriptedConnection {
+    type Response = http::Response<SdkBody>;
+    type Error = ConnectorError;
+    type Future =
+        Pin<Box<dyn std::future::Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: http::Request<SdkBody>) -> Self::Future {
+        let (parts, body) = req.into_parts();
+        let body = body.bytes().unwrap_or_default().to_vec();
+        self.requests
+            .lock()
+            .unwrap()
+            .push(http::Request::from_parts(parts, body));
+
+        let next = self
+            .script
+            .lock()
+            .unwrap()
+            .pop_front()
+            .expect("ScriptedConnection received more calls than were scripted");
+        Box::pin(async move { next })
+    }
+}